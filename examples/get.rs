@@ -1,7 +1,7 @@
 extern crate icndb;
 
 fn main() {
-    let client = icndb::ApiClient::new();
+    let client = icndb::ApiClient::new().expect("failed to create ICNDB client");
     let joke = client.get_by_id(23);
 
     if let Some(joke) = joke.ok() {
@@ -0,0 +1,185 @@
+//! A non-blocking counterpart to `ApiClient`, built on `hyper`'s async
+//! client and `futures` instead of blocking I/O.
+//!
+//! Response parsing and HTML-unescaping are shared with the blocking
+//! client (see `lib.rs`), so the two clients stay behavior-identical;
+//! only the transport differs. The blocking client is pinned to hyper
+//! 0.10 (its sync API); this module is pinned to hyper 0.11+ (the
+//! async API, no longer present in 0.10) under the `hyper_async` name
+//! declared in `lib.rs`, since one `hyper` version can't provide both.
+
+use futures::{Future, Stream};
+use hyper_async::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+use tokio_core::reactor::Handle;
+
+use super::{
+    build_category_query, encode_name, parse_response, unwrap_list_response, unwrap_response,
+    ApiCountResponseWrapper, ApiListResponseWrapper, ApiResponseWrapper, CategoryFilter, Error,
+    Joke, Result, PROTOCOL,
+};
+
+type HttpClient = ::hyper_async::Client<HttpsConnector<HttpConnector>>;
+
+/// A future resolving to a `Joke` or, failing that, an `Error`.
+pub type JokeFuture = Box<Future<Item = Joke, Error = Error>>;
+
+/// A future resolving to a `Vec<Joke>` or, failing that, an `Error`.
+pub type JokeListFuture = Box<Future<Item = Vec<Joke>, Error = Error>>;
+
+/// A future resolving to a `u64` or, failing that, an `Error`.
+pub type CountFuture = Box<Future<Item = u64, Error = Error>>;
+
+/// An async counterpart to `ApiClient`.
+///
+/// Every method returns a future instead of blocking the calling thread,
+/// which makes this client suitable for use inside an async runtime or
+/// for fetching many jokes concurrently.
+pub struct AsyncApiClient {
+    client: HttpClient,
+    host: String,
+}
+
+impl AsyncApiClient {
+    /// Create a new async API client bound to the given event loop.
+    ///
+    /// Returns an error if the TLS backend fails to initialize, rather
+    /// than panicking.
+    pub fn new(handle: &Handle) -> Result<AsyncApiClient> {
+        AsyncApiClient::builder(handle).build()
+    }
+
+    /// Start building an `AsyncApiClient` with a custom host, e.g. to
+    /// point at a local mock server instead of the live
+    /// `api.icndb.com` -- mirrors `ApiClient::builder`.
+    pub fn builder(handle: &Handle) -> AsyncApiClientBuilder {
+        AsyncApiClientBuilder::new(handle)
+    }
+
+    /// Get a random joke from the ICNDB.
+    pub fn next(&self) -> JokeFuture {
+        let request_url = format!("{}/jokes/random", self.host);
+        Box::new(self.fetch_joke(&request_url))
+    }
+
+    /// Get a random joke from the ICNDB, replacing the names in the joke.
+    pub fn next_with_names(&self, first: &str, last: &str) -> JokeFuture {
+        let request_url = format!(
+            "{}/jokes/random?firstName={}&lastName={}",
+            self.host,
+            encode_name(first),
+            encode_name(last),
+        );
+        Box::new(self.fetch_joke(&request_url))
+    }
+
+    /// Get a specific joke from the ICNDB.
+    pub fn get_by_id(&self, id: u64) -> JokeFuture {
+        let request_url = format!("{}/jokes/{}", self.host, id);
+        Box::new(self.fetch_joke(&request_url))
+    }
+
+    /// Get a specific joke with specified names.
+    pub fn get_by_id_with_names(&self, id: u64, first: &str, last: &str) -> JokeFuture {
+        let request_url = format!(
+            "{}/jokes/{}?firstName={}&lastName={}",
+            self.host,
+            id,
+            encode_name(first),
+            encode_name(last),
+        );
+        Box::new(self.fetch_joke(&request_url))
+    }
+
+    /// Get the number of jokes available in the ICNDB.
+    pub fn count(&self) -> CountFuture {
+        let request_url = format!("{}/jokes/count", self.host);
+        Box::new(self.fetch::<ApiCountResponseWrapper>(&request_url).map(|res| res.value))
+    }
+
+    /// Get every joke in the ICNDB.
+    pub fn all(&self) -> JokeListFuture {
+        let request_url = format!("{}/jokes", self.host);
+        Box::new(self.fetch_jokes(&request_url))
+    }
+
+    /// Get `count` random jokes from the ICNDB in a single call.
+    pub fn random_many(&self, count: u32) -> JokeListFuture {
+        self.random_many_in_categories(count, &CategoryFilter::default())
+    }
+
+    /// Get `count` random jokes, restricted to the given category filter.
+    pub fn random_many_in_categories(&self, count: u32, filter: &CategoryFilter) -> JokeListFuture {
+        let request_url = format!(
+            "{}/jokes/random/{}{}",
+            self.host,
+            count,
+            build_category_query(filter),
+        );
+        Box::new(self.fetch_jokes(&request_url))
+    }
+
+    /// Get a random joke, restricted to the given category filter.
+    pub fn next_in_categories(&self, filter: &CategoryFilter) -> JokeFuture {
+        let request_url = format!(
+            "{}/jokes/random{}",
+            self.host,
+            build_category_query(filter),
+        );
+        Box::new(self.fetch_joke(&request_url))
+    }
+
+    fn fetch_joke(&self, url: &str) -> impl Future<Item = Joke, Error = Error> {
+        self.fetch::<ApiResponseWrapper>(url)
+            .and_then(|wrapper| unwrap_response(Ok(wrapper)))
+    }
+
+    fn fetch_jokes(&self, url: &str) -> impl Future<Item = Vec<Joke>, Error = Error> {
+        self.fetch::<ApiListResponseWrapper>(url)
+            .and_then(|wrapper| unwrap_list_response(Ok(wrapper)))
+    }
+
+    fn fetch<T>(&self, url: &str) -> impl Future<Item = T, Error = Error>
+    where
+        T: ::serde::de::DeserializeOwned + 'static,
+    {
+        let uri = url.parse().expect("invalid request URL");
+
+        self.client
+            .get(uri)
+            .map_err(Error::from)
+            .and_then(|response| response.body().concat2().map_err(Error::from))
+            .and_then(|body| parse_response(&String::from_utf8_lossy(&body)))
+    }
+}
+
+/// Builds an `AsyncApiClient` with a custom host, mirroring
+/// `ApiClientBuilder`. Defaults to `api.icndb.com` over the protocol
+/// selected by the `ssl` feature.
+pub struct AsyncApiClientBuilder<'a> {
+    handle: &'a Handle,
+    host: Option<String>,
+}
+
+impl<'a> AsyncApiClientBuilder<'a> {
+    fn new(handle: &'a Handle) -> AsyncApiClientBuilder<'a> {
+        AsyncApiClientBuilder { handle, host: None }
+    }
+
+    /// Use the given host (e.g. `http://localhost:8080`) instead of the
+    /// live `api.icndb.com`.
+    pub fn host<S: Into<String>>(mut self, host: S) -> AsyncApiClientBuilder<'a> {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Build the `AsyncApiClient`, propagating any TLS setup failure as
+    /// `ErrorKind::Network` instead of panicking.
+    pub fn build(self) -> Result<AsyncApiClient> {
+        let connector = HttpsConnector::new(4, self.handle).map_err(Error::network)?;
+        let client = ::hyper_async::Client::configure().connector(connector).build(self.handle);
+        let host = self.host.unwrap_or_else(|| format!("{}://api.icndb.com", PROTOCOL));
+
+        Ok(AsyncApiClient { client, host })
+    }
+}
@@ -1,10 +1,10 @@
 extern crate icndb;
 
 fn main() {
-    let identifiers = std::env::args().skip(1).filter_map(|n| n.parse::<u64>().ok());
-    let client = icndb::ApiClient::new();
+    let identifiers: Vec<u64> = std::env::args().skip(1).filter_map(|n| n.parse::<u64>().ok()).collect();
+    let client = icndb::ApiClient::new().expect("failed to create ICNDB client");
 
-    for id in identifiers {
-        println!("{:?}", client.get_by_id(id));
+    for joke in client.get_by_ids(&identifiers) {
+        println!("{:?}", joke);
     }
 }
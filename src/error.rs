@@ -1,4 +1,7 @@
 use hyper;
+#[cfg(feature = "async")]
+use hyper_async;
+use serde_json;
 use std::error;
 use std::fmt;
 use std::io;
@@ -9,17 +12,22 @@ pub type Result<T> = result::Result<T, Error>;
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
-    cause: Option<Box<error::Error>>,
+    // `get_by_ids` sends `Result<Joke>` across a thread pool via
+    // `mpsc`, which requires `Error: Send`; a bare `Box<error::Error>`
+    // isn't, so the cause is stored as `Send + Sync` as well.
+    cause: Option<Box<error::Error + Send + Sync>>,
+    detail: Option<ProblemDetail>,
 }
 
 #[derive(Debug)]
 pub enum ErrorKind {
     /// An error returned by the ICNDB API.
     ///
-    /// The ICNDB API does not return useful error codes in most cases. It doesn't even return a 
+    /// The ICNDB API does not return useful error codes in most cases. It doesn't even return a
     /// JSON error response; it just spits out some nonsense about a call to an undefined method
-    /// (ChuckAPI::echoException()) in /home/alumni/mdecat/chuck/api-github... etc. etc. I'm 
-    /// guessing it's a bug that just isn't worth fixing.
+    /// (ChuckAPI::echoException()) in /home/alumni/mdecat/chuck/api-github... etc. etc. When this
+    /// happens, the raw response body is stashed on the `Error` as problem detail so callers
+    /// aren't left guessing; see `Error::detail`.
     Api,
 
     /// An error in decoding the API response.
@@ -33,13 +41,119 @@ pub enum ErrorKind {
     Network,
 }
 
+// Structured detail modeled on RFC 7807 problem+json, populated from
+// whatever the API actually sent back when we failed to decode a
+// proper payload. `type`/`title`/`status`/`detail` are filled in when
+// the body happens to be problem+json; otherwise `type`/`title`/`status`
+// are `None` and `detail` holds the raw text. `detail` also falls back
+// to the raw text when the JSON has no `detail` member, and `body`
+// always preserves the raw text for diagnostics.
+#[derive(Debug, Clone, Default)]
+struct ProblemDetail {
+    problem_type: Option<String>,
+    title: Option<String>,
+    status: Option<u16>,
+    detail: Option<String>,
+    body: String,
+}
+
+#[derive(Deserialize, Default)]
+struct ProblemJson {
+    #[serde(rename = "type")]
+    problem_type: Option<String>,
+    title: Option<String>,
+    status: Option<u16>,
+    detail: Option<String>,
+}
+
+impl ProblemDetail {
+    fn parse(body: &str) -> ProblemDetail {
+        match serde_json::from_str::<ProblemJson>(body) {
+            Ok(problem) => ProblemDetail {
+                problem_type: problem.problem_type,
+                title: problem.title,
+                status: problem.status,
+                // Fall back to the raw body when the JSON parsed but
+                // didn't carry a `detail` member of its own, so callers
+                // reaching for `Error::detail` still get something.
+                detail: problem.detail.or_else(|| Some(body.to_owned())),
+                body: body.to_owned(),
+            },
+            // Not JSON at all (e.g. the PHP stack trace the API is
+            // prone to dumping) -- stash the raw text as detail.
+            Err(_) => ProblemDetail {
+                problem_type: None,
+                title: None,
+                status: None,
+                detail: Some(body.to_owned()),
+                body: body.to_owned(),
+            },
+        }
+    }
+}
+
 impl Error {
     pub fn api() -> Error {
         Error {
             kind: ErrorKind::Api,
             cause: None,
+            detail: None,
+        }
+    }
+
+    // Builds an `Api` error from a response body that failed to decode
+    // as the expected payload, capturing whatever problem detail can be
+    // recovered from it.
+    pub(crate) fn api_with_body(body: &str) -> Error {
+        Error {
+            kind: ErrorKind::Api,
+            cause: None,
+            detail: Some(ProblemDetail::parse(body)),
+        }
+    }
+
+    // Wraps a lower-level error (e.g. TLS setup failure) as a `Network`
+    // error so callers don't have to deal with the underlying error type.
+    pub(crate) fn network<E>(cause: E) -> Error
+    where
+        E: error::Error + Send + Sync + 'static,
+    {
+        Error {
+            kind: ErrorKind::Network,
+            cause: Some(Box::new(cause)),
+            detail: None,
         }
     }
+
+    /// The RFC 7807 `type` URI describing this problem, if the API
+    /// supplied one.
+    pub fn problem_type(&self) -> Option<&str> {
+        self.detail.as_ref().and_then(|d| d.problem_type.as_ref().map(String::as_str))
+    }
+
+    /// The RFC 7807 `title` describing this problem, if the API
+    /// supplied one.
+    pub fn title(&self) -> Option<&str> {
+        self.detail.as_ref().and_then(|d| d.title.as_ref().map(String::as_str))
+    }
+
+    /// The HTTP status the API reported for this problem, if it
+    /// supplied one.
+    pub fn status(&self) -> Option<u16> {
+        self.detail.as_ref().and_then(|d| d.status)
+    }
+
+    /// The RFC 7807 `detail` describing this problem if the API
+    /// supplied one, otherwise the raw response body, if one was captured.
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_ref().and_then(|d| d.detail.as_ref().map(String::as_str))
+    }
+
+    /// The raw response body that produced this error, if one was
+    /// captured.
+    pub fn body(&self) -> Option<&str> {
+        self.detail.as_ref().map(|d| d.body.as_str())
+    }
 }
 
 impl From<hyper::Error> for Error {
@@ -47,6 +161,21 @@ impl From<hyper::Error> for Error {
         Error {
             kind: ErrorKind::Network,
             cause: Some(Box::new(error)),
+            detail: None,
+        }
+    }
+}
+
+// `AsyncApiClient` runs against the separately-versioned `hyper_async`
+// (hyper 0.11+) rather than the blocking client's `hyper` (0.10), so
+// its request/body errors need their own conversion.
+#[cfg(feature = "async")]
+impl From<hyper_async::Error> for Error {
+    fn from(error: hyper_async::Error) -> Self {
+        Error {
+            kind: ErrorKind::Network,
+            cause: Some(Box::new(error)),
+            detail: None,
         }
     }
 }
@@ -56,13 +185,26 @@ impl From<io::Error> for Error {
         Error {
             kind: ErrorKind::IO,
             cause: Some(Box::new(error)),
+            detail: None,
         }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", error::Error::description(self))
+        write!(f, "{}", error::Error::description(self))?;
+
+        if let Some(ref detail) = self.detail {
+            if let Some(ref title) = detail.title {
+                write!(f, ": {}", title)?;
+            }
+
+            if let Some(ref text) = detail.detail {
+                write!(f, ": {}", text)?;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -82,3 +224,29 @@ impl error::Error for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn detail_falls_back_to_raw_body_for_non_json() {
+        let error = Error::api_with_body("Fatal error: Call to undefined method ChuckAPI::echoException()");
+
+        assert_eq!(
+            error.detail(),
+            Some("Fatal error: Call to undefined method ChuckAPI::echoException()")
+        );
+    }
+
+    #[test]
+    fn detail_prefers_problem_json_fields() {
+        let error = Error::api_with_body(
+            r#"{"type":"about:blank","title":"Bad Request","status":400,"detail":"no such joke"}"#,
+        );
+
+        assert_eq!(error.title(), Some("Bad Request"));
+        assert_eq!(error.status(), Some(400));
+        assert_eq!(error.detail(), Some("no such joke"));
+    }
+}
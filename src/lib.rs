@@ -22,30 +22,82 @@ extern crate hyper_native_tls;
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg(feature = "async")]
+extern crate futures;
+
+// The blocking `ApiClient` below is written against hyper 0.10's sync
+// API (`Client::with_connector`, `hyper::net::HttpsConnector`, blocking
+// `.send()`); hyper 0.11 dropped that API entirely in favor of the
+// futures-based one `AsyncApiClient` needs. No single hyper version
+// satisfies both, so the `async` feature depends on hyper 0.11+ under
+// a second name -- see the `hyper_async = { package = "hyper", version
+// = "0.11" }` entry in Cargo.toml.
+#[cfg(feature = "async")]
+extern crate hyper_async;
+
+#[cfg(feature = "async")]
+extern crate hyper_tls;
+
+#[cfg(feature = "async")]
+extern crate tokio_core;
+
 extern crate hyper;
+#[macro_use]
+extern crate percent_encoding;
+extern crate serde;
 extern crate serde_json;
+extern crate threadpool;
 
 mod error;
 
 use hyper::client;
+use percent_encoding::{utf8_percent_encode, USERINFO_ENCODE_SET};
 use std::result;
+use std::sync::Arc;
 
 pub use error::*;
 
+#[cfg(feature = "async")]
+mod async_client;
+
+#[cfg(feature = "async")]
+pub use async_client::AsyncApiClient;
+
 #[cfg(not(feature="ssl"))]
-static PROTOCOL: &str = "http";
+pub(crate) static PROTOCOL: &str = "http";
 
 #[cfg(feature="ssl")]
-static PROTOCOL: &str = "https";
+pub(crate) static PROTOCOL: &str = "https";
 
+// Upper bound on the number of threads `ApiClient::get_by_ids` will use
+// to fetch a batch, regardless of how many IDs are requested.
+const MAX_BATCH_WORKERS: usize = 8;
+
+// This crate's `hyper::Client` (0.10.x) has no `Clone` impl of its own,
+// so the shared client is wrapped in an `Arc` -- cloning an `ApiClient`
+// (e.g. to hand one to each worker in `get_by_ids`) just bumps a
+// refcount on the existing connection pool rather than opening new
+// connections or reinitializing TLS.
+#[derive(Clone)]
 pub struct ApiClient {
-    client: hyper::Client,
+    client: Arc<hyper::Client>,
+    host: String,
 }
 
 impl ApiClient {
     /// Create a new API client.
-    pub fn new() -> ApiClient {
-        ApiClient { client: create_client() }
+    ///
+    /// Returns an error if the underlying HTTP client (and, with the
+    /// `ssl` feature, its TLS backend) fails to initialize.
+    pub fn new() -> Result<ApiClient> {
+        ApiClient::builder().build()
+    }
+
+    /// Start building an `ApiClient` with a custom host and/or a
+    /// caller-supplied `hyper::Client`, e.g. to point at a local mock
+    /// server instead of the live `api.icndb.com`.
+    pub fn builder() -> ApiClientBuilder {
+        ApiClientBuilder::default()
     }
 
     /// Get a random joke from the ICNDB.
@@ -53,7 +105,7 @@ impl ApiClient {
     /// Returns an option value containing a random joke from the API
     /// or, failing that, None.
     pub fn next(&self) -> Result<Joke> {
-        let request_url = format!("{}://api.icndb.com/jokes/random", PROTOCOL);
+        let request_url = format!("{}/jokes/random", self.host);
         let response = self.execute_request(&request_url);
         unwrap_response(response)
     }
@@ -64,7 +116,12 @@ impl ApiClient {
     /// using the names supplied to the function instead of the default
     /// name (Chuck Norris) or, failing that, None.
     pub fn next_with_names(&self, first: &str, last: &str) -> Result<Joke> {
-        let request_url = format!("{}://api.icndb.com/jokes/random?firstName={}&lastName={}", PROTOCOL, first, last);
+        let request_url = format!(
+            "{}/jokes/random?firstName={}&lastName={}",
+            self.host,
+            encode_name(first),
+            encode_name(last),
+        );
         let response = self.execute_request(&request_url);
         unwrap_response(response)
     }
@@ -74,7 +131,7 @@ impl ApiClient {
     /// Returns an option value containing a specified joke from the API
     /// or, failing that, None.
     pub fn get_by_id(&self, id: u64) -> Result<Joke> {
-        let request_url = format!("{}://api.icndb.com/jokes/{}", PROTOCOL, id);
+        let request_url = format!("{}/jokes/{}", self.host, id);
         let response = self.execute_request(&request_url);
         unwrap_response(response)
     }
@@ -85,33 +142,234 @@ impl ApiClient {
     /// using the names supplied to the function instead of the default
     /// name (Chuck Norris) or, failing that, None.
     pub fn get_by_id_with_names(&self, id: u64, first: &str, last: &str) -> Result<Joke> {
-        let request_url = format!("{}://api.icndb.com/jokes/{}?firstName={}&lastName={}", PROTOCOL, id, first, last);
+        let request_url = format!(
+            "{}/jokes/{}?firstName={}&lastName={}",
+            self.host,
+            id,
+            encode_name(first),
+            encode_name(last),
+        );
         let response = self.execute_request(&request_url);
         unwrap_response(response)
     }
 
-    fn execute_request(&self, url: &str) -> Result<ApiResponseWrapper> {
+    /// Get many jokes by ID, fetching them concurrently across a bounded
+    /// thread pool and collecting the results in input order.
+    ///
+    /// Each task clones `self` (cheap -- it just bumps the refcount on
+    /// the shared `Arc<hyper::Client>`, including a caller-supplied
+    /// client from `ApiClientBuilder::client`) rather than constructing
+    /// a fresh client per id. A worker that never reports back (e.g. the
+    /// pool drops a panicked task without retrying it) yields an `Err`
+    /// for that id instead of panicking the whole batch.
+    pub fn get_by_ids(&self, ids: &[u64]) -> Vec<Result<Joke>> {
+        use std::sync::mpsc;
+
+        if ids.is_empty() {
+            return Vec::new();
+        }
+
+        let pool = threadpool::ThreadPool::new(MAX_BATCH_WORKERS.min(ids.len()));
+        let (tx, rx) = mpsc::channel();
+
+        for (index, &id) in ids.iter().enumerate() {
+            let client = self.clone();
+            let tx = tx.clone();
+
+            pool.execute(move || {
+                let result = client.get_by_id(id);
+                // If the receiver's gone there's nothing left to report to.
+                let _ = tx.send((index, result));
+            });
+        }
+
+        drop(tx);
+
+        assemble_batch_results(ids, rx.into_iter().collect())
+    }
+
+    /// Get the number of jokes available in the ICNDB.
+    pub fn count(&self) -> Result<u64> {
+        let request_url = format!("{}/jokes/count", self.host);
+        let response: Result<ApiCountResponseWrapper> = self.execute_request(&request_url);
+        response.map(|res| res.value)
+    }
+
+    /// Get every joke in the ICNDB.
+    pub fn all(&self) -> Result<Vec<Joke>> {
+        let request_url = format!("{}/jokes", self.host);
+        let response = self.execute_request(&request_url);
+        unwrap_list_response(response)
+    }
+
+    /// Get `count` random jokes from the ICNDB in a single call.
+    pub fn random_many(&self, count: u32) -> Result<Vec<Joke>> {
+        self.random_many_in_categories(count, &CategoryFilter::default())
+    }
+
+    /// Get `count` random jokes, restricted to the given category filter.
+    pub fn random_many_in_categories(&self, count: u32, filter: &CategoryFilter) -> Result<Vec<Joke>> {
+        let request_url = format!(
+            "{}/jokes/random/{}{}",
+            self.host,
+            count,
+            build_category_query(filter),
+        );
+        let response = self.execute_request(&request_url);
+        unwrap_list_response(response)
+    }
+
+    /// Get a random joke, restricted to the given category filter.
+    pub fn next_in_categories(&self, filter: &CategoryFilter) -> Result<Joke> {
+        let request_url = format!(
+            "{}/jokes/random{}",
+            self.host,
+            build_category_query(filter),
+        );
+        let response = self.execute_request(&request_url);
+        unwrap_response(response)
+    }
+
+    fn execute_request<T>(&self, url: &str) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
         read_response(self.client.get(url).send())
     }
 }
 
+/// Builds an `ApiClient` with a custom host and/or HTTP client.
+///
+/// Defaults to `api.icndb.com` over the protocol selected by the `ssl`
+/// feature, and to a `hyper::Client` configured the same way
+/// `ApiClient::new` configures one.
+#[derive(Default)]
+pub struct ApiClientBuilder {
+    host: Option<String>,
+    client: Option<hyper::Client>,
+}
+
+impl ApiClientBuilder {
+    /// Use the given host (e.g. `http://localhost:8080`) instead of the
+    /// live `api.icndb.com`.
+    pub fn host<S: Into<String>>(mut self, host: S) -> ApiClientBuilder {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Use the given `hyper::Client` instead of one built by
+    /// `ApiClient`, e.g. to control timeouts or TLS configuration.
+    pub fn client(mut self, client: hyper::Client) -> ApiClientBuilder {
+        self.client = Some(client);
+        self
+    }
+
+    /// Build the `ApiClient`, propagating any TLS setup failure from
+    /// the default HTTP client as `ErrorKind::Network`.
+    pub fn build(self) -> Result<ApiClient> {
+        let client = match self.client {
+            Some(client) => client,
+            None => create_client()?,
+        };
+
+        let host = self.host.unwrap_or_else(|| format!("{}://api.icndb.com", PROTOCOL));
+
+        Ok(ApiClient { client: Arc::new(client), host })
+    }
+}
+
+/// A category filter for the random joke endpoints.
+///
+/// `include` corresponds to the ICNDB `limitTo` parameter and restricts
+/// jokes to the given categories; `exclude` corresponds to `exclude` and
+/// omits jokes belonging to the given categories. An empty slice leaves
+/// the corresponding parameter off the request entirely.
+#[derive(Debug, Default)]
+pub struct CategoryFilter<'a> {
+    pub include: &'a [&'a str],
+    pub exclude: &'a [&'a str],
+}
+
+// Builds the `limitTo`/`exclude` portion of a query string from a
+// category filter, including the leading `?` when non-empty.
+pub(crate) fn build_category_query(filter: &CategoryFilter) -> String {
+    let mut params = Vec::new();
+
+    if !filter.include.is_empty() {
+        params.push(format!("limitTo=[{}]", encode_category_list(filter.include)));
+    }
+
+    if !filter.exclude.is_empty() {
+        params.push(format!("exclude=[{}]", encode_category_list(filter.exclude)));
+    }
+
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", params.join("&"))
+    }
+}
+
+// Restores the input order of `ApiClient::get_by_ids`'s per-id results,
+// which arrive out of order from the thread pool as `(index, result)`
+// pairs. An id whose worker never reported back gets a synthesized
+// `Err` rather than leaving a hole in the output.
+fn assemble_batch_results(ids: &[u64], received: Vec<(usize, Result<Joke>)>) -> Vec<Result<Joke>> {
+    let mut results: Vec<Option<Result<Joke>>> = (0..ids.len()).map(|_| None).collect();
+
+    for (index, result) in received {
+        results[index] = Some(result);
+    }
+
+    results
+        .into_iter()
+        .zip(ids)
+        .map(|(result, &id)| {
+            result.unwrap_or_else(|| {
+                Err(Error::api_with_body(&format!(
+                    "no result was returned for joke id {} (worker thread may have been lost)",
+                    id,
+                )))
+            })
+        })
+        .collect()
+}
+
+fn encode_category_list(categories: &[&str]) -> String {
+    categories.iter().map(|c| encode_name(c)).collect::<Vec<_>>().join(",")
+}
+
 // Wraps an API response from the `api.icndb.com`. The authors'
 // intent appears to have been to provide an interface for both
 // failed and successful requests, but it has been difficult to
 // represent the full wrapper in Rust, and the wrapper adds no
 // real value.
 #[derive(Deserialize)]
-struct ApiResponseWrapper {
+pub(crate) struct ApiResponseWrapper {
     value: ApiResponse,
 }
 
 #[derive(Deserialize)]
-struct ApiResponse {
+pub(crate) struct ApiResponse {
     pub id: u64,
     pub joke: String,
     pub categories: Box<[String]>,
 }
 
+// Wraps a list-valued response, e.g. `GET /jokes` or `GET /jokes/random/N`,
+// where `value` is an array of jokes rather than a single object.
+#[derive(Deserialize)]
+pub(crate) struct ApiListResponseWrapper {
+    value: Vec<ApiResponse>,
+}
+
+// Wraps the response from `GET /jokes/count`, where `value` is a bare
+// integer rather than an object or array.
+#[derive(Deserialize)]
+pub(crate) struct ApiCountResponseWrapper {
+    value: u64,
+}
+
 /// Response containing a Chuck Norris joke.
 ///
 /// Represents a single joke provided by the ICNDB. The `id` field
@@ -135,12 +393,41 @@ impl From<ApiResponse> for Joke {
     }
 }
 
+// `define_encode_set!`'s macro rules only accept a bare `pub` item, not
+// `pub(crate)`, so the set is kept in its own private module and referred
+// to through that module's path.
+mod name_encode_set {
+    use percent_encoding::USERINFO_ENCODE_SET;
+
+    define_encode_set! {
+        // `QUERY_ENCODE_SET` deliberately leaves `&` and `=` alone, since
+        // those are the delimiters a real query string needs; that's
+        // exactly wrong for a value being interpolated *into* one, so we
+        // widen `USERINFO_ENCODE_SET` with `&`, `+`, `%` and the other
+        // sub-delimiters it leaves alone, so every character outside the
+        // unreserved set is encoded.
+        pub NAME_ENCODE_SET = [USERINFO_ENCODE_SET] | {'&', '=', '+', '%', '!', '$', '\'', '(', ')', '*', ','}
+    }
+}
+
+// Percent-encodes a name segment so that spaces, `&`, `=`, `#`, and other
+// characters outside the unreserved set can't break the query string we
+// build it into.
+pub(crate) fn encode_name(name: &str) -> String {
+    utf8_percent_encode(name, name_encode_set::NAME_ENCODE_SET).to_string()
+}
+
 // Parses the response returned by a query against the ICNDB API
 // into an Joke or, failing that, None.
-fn unwrap_response(response: Result<ApiResponseWrapper>) -> Result<Joke> {
+pub(crate) fn unwrap_response(response: Result<ApiResponseWrapper>) -> Result<Joke> {
     response.map(|res| unescape_content(res.value))
 }
 
+// Parses a list-valued response from the ICNDB API into a `Vec<Joke>`.
+pub(crate) fn unwrap_list_response(response: Result<ApiListResponseWrapper>) -> Result<Vec<Joke>> {
+    response.map(|res| res.value.into_iter().map(unescape_content).collect())
+}
+
 // Unescape HTML entities found in joke contents.
 //
 // The ICNDB represents some values as HTML entities in the json
@@ -155,7 +442,7 @@ fn unwrap_response(response: Result<ApiResponseWrapper>) -> Result<Joke> {
 // - &quot;
 //
 // Hopefully we won't discover anymore.
-fn unescape_content(response: ApiResponse) -> Joke {
+pub(crate) fn unescape_content(response: ApiResponse) -> Joke {
     if response.joke.contains("&quot;") {
         Joke {
             id: response.id,
@@ -168,38 +455,106 @@ fn unescape_content(response: ApiResponse) -> Joke {
 }
 
 #[cfg(not(feature="ssl"))]
-fn create_client() -> hyper::Client {
-    Client::new()
+fn create_client() -> Result<hyper::Client> {
+    Ok(hyper::Client::new())
 }
 
 #[cfg(feature="ssl")]
-fn create_client() -> hyper::Client {
+fn create_client() -> Result<hyper::Client> {
     use hyper::net::HttpsConnector;
     use hyper_native_tls::NativeTlsClient;
 
-    let ssl = NativeTlsClient::new().unwrap();
+    let ssl = NativeTlsClient::new().map_err(Error::network)?;
     let connector = HttpsConnector::new(ssl);
 
-    hyper::Client::with_connector(connector)
+    Ok(hyper::Client::with_connector(connector))
 }
 
-fn read_response(response: result::Result<client::Response, hyper::Error>) -> Result<ApiResponseWrapper> {
+fn read_response<T>(response: result::Result<client::Response, hyper::Error>) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
     use std::io::Read;
 
     let mut buf = String::new();
     response?.read_to_string(&mut buf)?;
+    parse_response(&buf)
+}
 
-    match serde_json::from_str(&buf) {
+// Decodes a JSON response body shared by both the blocking and async
+// clients, so the two stay behavior-identical.
+pub(crate) fn parse_response<T>(buf: &str) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    match serde_json::from_str(buf) {
         Ok(result) => Ok(result),
-        Err(_) => Err(Error::api()),
+        Err(_) => Err(Error::api_with_body(buf)),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{assemble_batch_results, build_category_query, encode_name, CategoryFilter, Joke};
+
     #[test]
     fn it_works() {
         let result = super::next();
         assert!(result.is_ok(), format!("{:?}", result));
     }
+
+    #[test]
+    fn encode_name_escapes_query_delimiters() {
+        let encoded = encode_name("Bob & Ray = #1");
+
+        assert!(!encoded.contains('&'));
+        assert!(!encoded.contains('='));
+        assert!(!encoded.contains('#'));
+        assert!(!encoded.contains(' '));
+    }
+
+    #[test]
+    fn encode_name_round_trips() {
+        use percent_encoding::percent_decode;
+
+        let encoded = encode_name("Von & Neumann = Chuck #1");
+        let decoded = percent_decode(encoded.as_bytes()).decode_utf8().unwrap();
+
+        assert_eq!(decoded, "Von & Neumann = Chuck #1");
+    }
+
+    #[test]
+    fn build_category_query_is_empty_without_filters() {
+        assert_eq!(build_category_query(&CategoryFilter::default()), "");
+    }
+
+    #[test]
+    fn build_category_query_includes_limit_to_and_exclude() {
+        let filter = CategoryFilter {
+            include: &["nerdy"],
+            exclude: &["explicit"],
+        };
+
+        assert_eq!(build_category_query(&filter), "?limitTo=[nerdy]&exclude=[explicit]");
+    }
+
+    #[test]
+    fn assemble_batch_results_restores_input_order() {
+        let ids = [1, 2, 3];
+        let received = vec![(2, joke(3)), (0, joke(1))];
+
+        let results = assemble_batch_results(&ids, received);
+
+        assert_eq!(results[0].as_ref().unwrap().id, 1);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().id, 3);
+    }
+
+    fn joke(id: u64) -> super::Result<Joke> {
+        Ok(Joke {
+            id,
+            content: String::new(),
+            categories: Box::new([]),
+        })
+    }
 }